@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
     time::Duration,
 };
@@ -9,6 +9,8 @@ use bstr::BStr;
 use network_interface::NetworkInterfaceConfig;
 use parking_lot::Mutex;
 use tokio::net::UdpSocket;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, trace, warn};
 
@@ -22,6 +24,30 @@ const SSDP_MCAST_IPV6_SITE_LOCAL: Ipv6Addr = Ipv6Addr::new(0xff05, 0, 0, 0, 0, 0
 const NTS_ALIVE: &str = "ssdp:alive";
 const NTS_BYEBYE: &str = "ssdp:byebye";
 
+const MDNS_PORT: u16 = 5353;
+const MDNS_MCAST_IPV4: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_MCAST_IPV6: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb);
+
+// The DNS-SD service type we advertise the rqbit HTTP API under. Clients such as
+// Bonjour/Avahi browse for this to discover the server.
+const MDNS_SERVICE_TYPE: &str = "_http._tcp.local";
+
+const DNS_TYPE_A: u16 = 1;
+const DNS_TYPE_PTR: u16 = 12;
+const DNS_TYPE_TXT: u16 = 16;
+const DNS_TYPE_AAAA: u16 = 28;
+const DNS_TYPE_SRV: u16 = 33;
+const DNS_TYPE_ANY: u16 = 255;
+
+const DNS_CLASS_IN: u16 = 1;
+// Top bit of the rrclass in a response means "cache-flush" (RFC 6762 §10.2).
+const DNS_CACHE_FLUSH: u16 = 0x8000;
+
+const MDNS_TTL: u32 = 120;
+
+// How often to re-enumerate interfaces and reconcile multicast memberships.
+const MEMBERSHIP_RESYNC_INTERVAL: Duration = Duration::from_secs(30);
+
 fn ipv6_is_link_local(ip: Ipv6Addr) -> bool {
     let s = ip.segments();
     [s[0], s[1], s[2], s[3]] == [0xfe80, 0, 0, 0]
@@ -30,9 +56,7 @@ fn ipv6_is_link_local(ip: Ipv6Addr) -> bool {
 #[derive(Debug)]
 pub enum SsdpMessage<'a, 'h> {
     MSearch(SsdpMSearchRequest<'a>),
-    #[allow(dead_code)]
     OtherRequest(httparse::Request<'h, 'a>),
-    #[allow(dead_code)]
     Response(httparse::Response<'h, 'a>),
 }
 
@@ -99,11 +123,69 @@ pub fn try_parse_ssdp<'a, 'h>(
     }
 }
 
+// Fallback TTL used for discovered devices that don't send a Cache-Control header.
+const DEFAULT_DEVICE_MAX_AGE: u64 = 1800;
+// Upper bound on a device's advertised max-age (1 day), to keep a hostile or
+// buggy Cache-Control value from overflowing the expiry Instant.
+const MAX_DEVICE_MAX_AGE: u64 = 86_400;
+// Capacity of the device-event broadcast channel.
+const DEVICE_EVENT_CHANNEL_CAP: usize = 16;
+
+/// A UPnP device discovered on the local network via an `ssdp:alive` NOTIFY or a
+/// `200 OK` M-SEARCH response.
+#[derive(Clone, Debug)]
+pub struct DiscoveredDevice {
+    /// Unique Service Name, the registry key.
+    pub usn: String,
+    /// The advertised service/device type (`ST` in responses, `NT` in NOTIFYs).
+    pub kind: String,
+    /// The device description `LOCATION` URL.
+    pub location: String,
+    /// The device's `Server` string, if it sent one.
+    pub server: Option<String>,
+    /// When this entry ages out absent a refreshing alive/response.
+    pub valid_until: Instant,
+}
+
+/// Emitted on the discovery channel as the device registry changes.
+#[derive(Clone, Debug)]
+pub enum DeviceEvent {
+    /// A device was discovered or refreshed.
+    Discovered(DiscoveredDevice),
+    /// A device expired or sent `ssdp:byebye`; carries its USN.
+    Lost(String),
+}
+
+fn find_header<'a>(headers: &'a [httparse::Header<'_>], name: &str) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value)
+}
+
+/// Parse `max-age` out of a `Cache-Control` header value.
+fn parse_max_age(value: &[u8]) -> Option<u64> {
+    let s = std::str::from_utf8(value).ok()?;
+    for part in s.split(',') {
+        let part = part.trim();
+        if let Some(rest) = part.strip_prefix("max-age") {
+            return rest.trim_start().strip_prefix('=')?.trim().parse().ok();
+        }
+    }
+    None
+}
+
 pub struct SsdpRunnerOptions {
     pub usn: String,
     pub description_http_location: url::Url,
     pub server_string: String,
     pub notify_interval: Duration,
+    // TTL / hop limit for outgoing multicast. SSDP is site-scoped, so this
+    // should be small (the UPnP spec recommends 4) rather than the OS default.
+    pub multicast_ttl: u32,
+    // Whether outgoing multicast is looped back to this host. Normally off:
+    // when on, rqbit receives (and has to discard) its own M-SEARCH packets.
+    pub multicast_loop: bool,
     pub shutdown: CancellationToken,
 }
 
@@ -111,6 +193,9 @@ pub struct SsdpRunner {
     opts: SsdpRunnerOptions,
     socket_v4: Option<UdpSocket>,
     socket_v6: Option<UdpSocket>,
+    memberships: Mutex<HashSet<Membership>>,
+    registry: Mutex<HashMap<String, DiscoveredDevice>>,
+    device_events: broadcast::Sender<DeviceEvent>,
 }
 
 fn socket_presetup(bind_addr: SocketAddr) -> anyhow::Result<tokio::net::UdpSocket> {
@@ -141,72 +226,97 @@ fn socket_presetup(bind_addr: SocketAddr) -> anyhow::Result<tokio::net::UdpSocke
 }
 
 async fn bind_v4_socket() -> anyhow::Result<UdpSocket> {
+    // Multicast group memberships are managed by SsdpRunner so they can be
+    // re-synced as interfaces come and go; see sync_memberships.
     let bind_addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SSDP_PORT);
-    let socket = socket_presetup(bind_addr.into())?;
-
-    let default_multiast_membership_ip = std::iter::once(Ipv4Addr::UNSPECIFIED);
-    let all_multicast_membership_ips = network_interface::NetworkInterface::show()
-        .into_iter()
-        .flatten()
-        .flat_map(|nic| nic.addr.into_iter())
-        .filter_map(|addr| {
-            let ip = addr.ip();
-            match ip {
-                std::net::IpAddr::V4(addr) if addr.is_private() && !addr.is_loopback() => {
-                    Some(addr)
-                }
-                _ => None,
-            }
-        });
-
-    for ifaddr in default_multiast_membership_ip.chain(all_multicast_membership_ips) {
-        trace!(multiaddr=?SSDM_MCAST_IPV4, interface=?ifaddr, "joining multicast v4 group");
-        if let Err(e) = socket.join_multicast_v4(SSDM_MCAST_IPV4, ifaddr) {
-            debug!(multiaddr=?SSDM_MCAST_IPV4, interface=?ifaddr, "error joining multicast v4 group: {e:#}");
-        }
-    }
-
-    Ok(socket)
+    socket_presetup(bind_addr.into())
 }
 
 async fn bind_v6_socket() -> anyhow::Result<UdpSocket> {
     let bind_addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, SSDP_PORT, 0, 0);
-    let socket = socket_presetup(bind_addr.into())?;
+    socket_presetup(bind_addr.into())
+}
+
+/// A single multicast group membership on a specific interface, tracked so it
+/// can be dropped and re-established as interfaces change.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Membership {
+    V4 { group: Ipv4Addr, iface: Ipv4Addr },
+    V6 { group: Ipv6Addr, ifindex: u32 },
+}
 
+/// The set of memberships rqbit wants to hold given the interfaces present now.
+fn desired_memberships() -> HashSet<Membership> {
+    let mut set = HashSet::new();
+    // The unspecified interface keeps us reachable even before any NIC is enumerated.
+    set.insert(Membership::V4 {
+        group: SSDM_MCAST_IPV4,
+        iface: Ipv4Addr::UNSPECIFIED,
+    });
     for nic in network_interface::NetworkInterface::show()
         .into_iter()
         .flatten()
     {
-        let mut has_link_local = false;
-        let mut has_site_local = false;
         for addr in nic.addr.iter() {
-            let addr = match addr.ip() {
-                IpAddr::V4(_) => continue,
-                IpAddr::V6(v6) => v6,
-            };
-            if addr.is_loopback() {
-                continue;
-            }
-            if ipv6_is_link_local(addr) {
-                has_link_local = true;
-            } else {
-                has_site_local = true;
-            }
-        }
-        for (present, multiaddr) in [
-            (has_link_local, SSDP_MCAST_IPV6_LINK_LOCAL),
-            (has_site_local, SSDP_MCAST_IPV6_SITE_LOCAL),
-        ] {
-            if !present {
-                continue;
-            }
-            if let Err(e) = socket.join_multicast_v6(&multiaddr, nic.index) {
-                debug!(multiaddr=?multiaddr, interface=?nic.index, "error joining multicast v6 group: {e:#}");
+            match addr.ip() {
+                IpAddr::V4(a) if a.is_private() && !a.is_loopback() => {
+                    set.insert(Membership::V4 {
+                        group: SSDM_MCAST_IPV4,
+                        iface: a,
+                    });
+                }
+                IpAddr::V6(a) if !a.is_loopback() => {
+                    let group = if ipv6_is_link_local(a) {
+                        SSDP_MCAST_IPV6_LINK_LOCAL
+                    } else {
+                        SSDP_MCAST_IPV6_SITE_LOCAL
+                    };
+                    set.insert(Membership::V6 {
+                        group,
+                        ifindex: nic.index,
+                    });
+                }
+                _ => {}
             }
         }
     }
+    set
+}
 
-    Ok(socket)
+/// Low-level `setsockopt` with a raw option buffer, used for the scoped
+/// drop-membership calls that tokio's `UdpSocket` does not expose.
+fn raw_setsockopt(sock: &UdpSocket, level: i32, optname: i32, buf: &[u8]) -> anyhow::Result<()> {
+    let ret: i32;
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::io::AsRawSocket;
+        ret = unsafe {
+            winapi::um::winsock2::setsockopt(
+                sock.as_raw_socket().try_into()?,
+                level,
+                optname,
+                buf.as_ptr() as _,
+                buf.len().try_into()?,
+            )
+        };
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::fd::{AsFd, AsRawFd};
+        ret = unsafe {
+            libc::setsockopt(
+                sock.as_fd().as_raw_fd(),
+                level,
+                optname,
+                buf.as_ptr() as _,
+                buf.len().try_into()?,
+            )
+        };
+    }
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
 }
 
 struct MulticastOpts {
@@ -257,6 +367,116 @@ fn set_mcast_if(sock: &UdpSocket, local_ip: Ipv4Addr) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[derive(Clone, Copy)]
+enum McastOpt {
+    V4Ttl,
+    V4Loop,
+    V6Hops,
+    V6Loop,
+}
+
+/// Set an `IP_MULTICAST_TTL`/`_LOOP` (or their IPv6 `IPV6_MULTICAST_HOPS`/`_LOOP`
+/// counterparts) socket option.
+///
+/// The IPv4 options are a 4-byte `c_int` on Linux and Windows but a single
+/// `c_uchar` on the BSDs, macOS and Solaris; passing the wrong width makes the
+/// call fail silently or error on those platforms, so we branch on `target_os`.
+/// The IPv6 options are always a `c_int`.
+fn set_mcast_opt(sock: &UdpSocket, opt: McastOpt, value: u32) -> anyhow::Result<()> {
+    #[cfg(not(target_os = "windows"))]
+    let (level, optname) = match opt {
+        McastOpt::V4Ttl => (libc::IPPROTO_IP, libc::IP_MULTICAST_TTL),
+        McastOpt::V4Loop => (libc::IPPROTO_IP, libc::IP_MULTICAST_LOOP),
+        McastOpt::V6Hops => (libc::IPPROTO_IPV6, libc::IPV6_MULTICAST_HOPS),
+        McastOpt::V6Loop => (libc::IPPROTO_IPV6, libc::IPV6_MULTICAST_LOOP),
+    };
+    #[cfg(target_os = "windows")]
+    let (level, optname) = match opt {
+        McastOpt::V4Ttl => (
+            winapi::shared::ws2def::IPPROTO_IP,
+            winapi::shared::ws2ipdef::IP_MULTICAST_TTL,
+        ),
+        McastOpt::V4Loop => (
+            winapi::shared::ws2def::IPPROTO_IP,
+            winapi::shared::ws2ipdef::IP_MULTICAST_LOOP,
+        ),
+        McastOpt::V6Hops => (
+            winapi::shared::ws2def::IPPROTO_IPV6,
+            winapi::shared::ws2ipdef::IPV6_MULTICAST_HOPS,
+        ),
+        McastOpt::V6Loop => (
+            winapi::shared::ws2def::IPPROTO_IPV6,
+            winapi::shared::ws2ipdef::IPV6_MULTICAST_LOOP,
+        ),
+    };
+
+    let is_v6 = matches!(opt, McastOpt::V6Hops | McastOpt::V6Loop);
+
+    let ret: i32;
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::io::AsRawSocket;
+        // DWORD-sized on Windows for both v4 and v6.
+        let v = value as winapi::ctypes::c_int;
+        ret = unsafe {
+            winapi::um::winsock2::setsockopt(
+                sock.as_raw_socket().try_into()?,
+                level,
+                optname,
+                &v as *const _ as _,
+                std::mem::size_of_val(&v).try_into()?,
+            )
+        };
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::fd::{AsFd, AsRawFd};
+        // The IPv4 TTL/LOOP options take a single byte on the BSDs/macOS/Solaris.
+        let byte_width = !is_v6
+            && cfg!(any(
+                target_os = "macos",
+                target_os = "ios",
+                target_os = "tvos",
+                target_os = "watchos",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd",
+                target_os = "dragonfly",
+                target_os = "solaris",
+                target_os = "illumos",
+            ));
+        let fd = sock.as_fd().as_raw_fd();
+        ret = if byte_width {
+            let v = value as libc::c_uchar;
+            unsafe {
+                libc::setsockopt(
+                    fd,
+                    level,
+                    optname,
+                    &v as *const _ as _,
+                    std::mem::size_of_val(&v).try_into()?,
+                )
+            }
+        } else {
+            let v = value as libc::c_int;
+            unsafe {
+                libc::setsockopt(
+                    fd,
+                    level,
+                    optname,
+                    &v as *const _ as _,
+                    std::mem::size_of_val(&v).try_into()?,
+                )
+            }
+        };
+    }
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let _ = is_v6;
+    Ok(())
+}
+
 impl MulticastOpts {
     fn addr_no_scope(&self) -> SocketAddr {
         let mut addr = self.addr;
@@ -277,11 +497,247 @@ impl SsdpRunner {
             .await
             .map_err(|e| warn!("error creating IPv6 SSDP socket: {e:#}"))
             .ok();
-        Ok(Self {
+
+        if let Some(sock) = socket_v4.as_ref() {
+            for (opt, value) in [
+                (McastOpt::V4Ttl, opts.multicast_ttl),
+                (McastOpt::V4Loop, opts.multicast_loop as u32),
+            ] {
+                if let Err(e) = set_mcast_opt(sock, opt, value) {
+                    debug!("error setting IPv4 multicast option: {e:#}");
+                }
+            }
+        }
+        if let Some(sock) = socket_v6.as_ref() {
+            for (opt, value) in [
+                (McastOpt::V6Hops, opts.multicast_ttl),
+                (McastOpt::V6Loop, opts.multicast_loop as u32),
+            ] {
+                if let Err(e) = set_mcast_opt(sock, opt, value) {
+                    debug!("error setting IPv6 multicast option: {e:#}");
+                }
+            }
+        }
+
+        let (device_events, _) = broadcast::channel(DEVICE_EVENT_CHANNEL_CAP);
+        let runner = Self {
             opts,
             socket_v4,
             socket_v6,
-        })
+            memberships: Mutex::new(HashSet::new()),
+            registry: Mutex::new(HashMap::new()),
+            device_events,
+        };
+        runner.sync_memberships();
+        Ok(runner)
+    }
+
+    /// Subscribe to [`DeviceEvent`]s as devices are discovered and lost.
+    pub fn subscribe(&self) -> broadcast::Receiver<DeviceEvent> {
+        self.device_events.subscribe()
+    }
+
+    /// A snapshot of the devices discovered so far.
+    pub fn discovered_devices(&self) -> Vec<DiscoveredDevice> {
+        self.registry.lock().values().cloned().collect()
+    }
+
+    fn register_device(
+        &self,
+        usn: String,
+        kind: String,
+        location: String,
+        server: Option<String>,
+        max_age: u64,
+    ) {
+        let dev = DiscoveredDevice {
+            usn: usn.clone(),
+            kind,
+            location,
+            server,
+            // max_age comes verbatim from an untrusted Cache-Control header, so
+            // cap it before turning it into a deadline to avoid overflowing the
+            // Instant addition.
+            valid_until: Instant::now()
+                + Duration::from_secs(max_age.min(MAX_DEVICE_MAX_AGE)),
+        };
+        trace!(?dev, "discovered/refreshed UPnP device");
+        self.registry.lock().insert(usn, dev.clone());
+        let _ = self.device_events.send(DeviceEvent::Discovered(dev));
+    }
+
+    fn remove_device(&self, usn: &str) {
+        if self.registry.lock().remove(usn).is_some() {
+            trace!(usn, "removing UPnP device from registry");
+            let _ = self.device_events.send(DeviceEvent::Lost(usn.to_owned()));
+        }
+    }
+
+    /// Register a device from the headers of a `200 OK` response or an
+    /// `ssdp:alive` NOTIFY. `kind_header` names the header (`ST` or `NT`) whose
+    /// value becomes the device's `kind`.
+    fn register_from_headers(&self, headers: &[httparse::Header<'_>], kind_header: &str) {
+        let usn = find_header(headers, "USN").and_then(|v| std::str::from_utf8(v).ok());
+        let kind = find_header(headers, kind_header).and_then(|v| std::str::from_utf8(v).ok());
+        let location = find_header(headers, "LOCATION").and_then(|v| std::str::from_utf8(v).ok());
+        let (usn, kind, location) = match (usn, kind, location) {
+            (Some(u), Some(k), Some(l)) => (u.to_owned(), k.to_owned(), l.to_owned()),
+            _ => {
+                trace!("ignoring device advert missing USN/type/LOCATION");
+                return;
+            }
+        };
+        let server = find_header(headers, "SERVER")
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .map(|s| s.to_owned());
+        let max_age = find_header(headers, "CACHE-CONTROL")
+            .and_then(parse_max_age)
+            .unwrap_or(DEFAULT_DEVICE_MAX_AGE);
+        self.register_device(usn, kind, location, server, max_age);
+    }
+
+    async fn task_expire_devices(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            let now = Instant::now();
+            let expired: Vec<String> = self
+                .registry
+                .lock()
+                .iter()
+                .filter(|(_, d)| d.valid_until <= now)
+                .map(|(usn, _)| usn.clone())
+                .collect();
+            for usn in expired {
+                self.remove_device(&usn);
+            }
+        }
+    }
+
+    /// Join a single multicast group membership, returning whether it succeeded.
+    fn join_membership(&self, m: &Membership) -> bool {
+        match m {
+            Membership::V4 { group, iface } => match self.socket_v4.as_ref() {
+                Some(sock) => {
+                    trace!(multiaddr=?group, interface=?iface, "joining multicast v4 group");
+                    if let Err(e) = sock.join_multicast_v4(*group, *iface) {
+                        debug!(multiaddr=?group, interface=?iface, "error joining multicast v4 group: {e:#}");
+                        false
+                    } else {
+                        true
+                    }
+                }
+                None => false,
+            },
+            Membership::V6 { group, ifindex } => match self.socket_v6.as_ref() {
+                Some(sock) => {
+                    trace!(multiaddr=?group, interface=?ifindex, "joining multicast v6 group");
+                    if let Err(e) = sock.join_multicast_v6(group, *ifindex) {
+                        debug!(multiaddr=?group, interface=?ifindex, "error joining multicast v6 group: {e:#}");
+                        false
+                    } else {
+                        true
+                    }
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Drop a single multicast group membership via a raw drop-membership call.
+    fn drop_membership(&self, m: &Membership) {
+        let res = match m {
+            Membership::V4 { group, iface } => match self.socket_v4.as_ref() {
+                Some(sock) => {
+                    // struct ip_mreq { in_addr imr_multiaddr; in_addr imr_interface; }
+                    let mut buf = [0u8; 8];
+                    buf[0..4].copy_from_slice(&group.octets());
+                    buf[4..8].copy_from_slice(&iface.octets());
+                    #[cfg(not(target_os = "windows"))]
+                    let (level, optname) = (libc::IPPROTO_IP, libc::IP_DROP_MEMBERSHIP);
+                    #[cfg(target_os = "windows")]
+                    let (level, optname) = (
+                        winapi::shared::ws2def::IPPROTO_IP,
+                        winapi::shared::ws2ipdef::IP_DROP_MEMBERSHIP,
+                    );
+                    Some(raw_setsockopt(sock, level, optname, &buf))
+                }
+                None => None,
+            },
+            Membership::V6 { group, ifindex } => match self.socket_v6.as_ref() {
+                Some(sock) => {
+                    // struct ipv6_mreq { in6_addr ipv6mr_multiaddr; unsigned ipv6mr_interface; }
+                    let mut buf = [0u8; 20];
+                    buf[0..16].copy_from_slice(&group.octets());
+                    buf[16..20].copy_from_slice(&ifindex.to_ne_bytes());
+                    #[cfg(not(target_os = "windows"))]
+                    let (level, optname) = (libc::IPPROTO_IPV6, libc::IPV6_DROP_MEMBERSHIP);
+                    #[cfg(target_os = "windows")]
+                    let (level, optname) = (
+                        winapi::shared::ws2def::IPPROTO_IPV6,
+                        winapi::shared::ws2ipdef::IPV6_DROP_MEMBERSHIP,
+                    );
+                    Some(raw_setsockopt(sock, level, optname, &buf))
+                }
+                None => None,
+            },
+        };
+        if let Some(Err(e)) = res {
+            debug!(membership=?m, "error dropping multicast membership: {e:#}");
+        } else if res.is_some() {
+            trace!(membership=?m, "dropped multicast membership");
+        }
+    }
+
+    /// Reconcile the held memberships with the interfaces present now: join any
+    /// newly-seen group/interface pairs and drop any that have vanished.
+    /// Returns true if at least one new membership was joined.
+    fn sync_memberships(&self) -> bool {
+        let desired = desired_memberships();
+        let mut current = self.memberships.lock();
+
+        let mut joined_new = false;
+        for m in desired.iter() {
+            if !current.contains(m) && self.join_membership(m) {
+                current.insert(*m);
+                joined_new = true;
+            }
+        }
+
+        let to_drop: Vec<Membership> = current
+            .iter()
+            .filter(|m| !desired.contains(m))
+            .copied()
+            .collect();
+        for m in to_drop {
+            self.drop_membership(&m);
+            current.remove(&m);
+        }
+
+        joined_new
+    }
+
+    /// Drop every held membership, used on shutdown.
+    fn drop_all_memberships(&self) {
+        let mut current = self.memberships.lock();
+        for m in current.iter() {
+            self.drop_membership(m);
+        }
+        current.clear();
+    }
+
+    async fn task_resync_memberships_periodically(&self) {
+        let mut interval = tokio::time::interval(MEMBERSHIP_RESYNC_INTERVAL);
+        // The initial sync already happened in new(); skip the immediate tick.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if self.sync_memberships() {
+                // A new interface appeared: announce right away so freshly
+                // connected clients don't wait for the next notify_interval.
+                self.try_send_notifies(NTS_ALIVE).await;
+            }
+        }
     }
 
     fn generate_notify_message(&self, kind: &str, nts: &str, opts: &MulticastOpts) -> String {
@@ -434,6 +890,29 @@ Content-Length: 0\r\n\r\n"
         let parsed = try_parse_ssdp(msg, &mut headers);
         let msg = match parsed {
             Ok(SsdpMessage::MSearch(msg)) => msg,
+            Ok(SsdpMessage::Response(resp)) => {
+                // A 200 OK response to one of our M-SEARCHes: record the device.
+                if resp.code == Some(200) {
+                    self.register_from_headers(resp.headers, "ST");
+                }
+                return Ok(());
+            }
+            Ok(SsdpMessage::OtherRequest(req)) if req.method == Some("NOTIFY") => {
+                match find_header(req.headers, "NTS") {
+                    Some(nts) if nts == NTS_ALIVE.as_bytes() => {
+                        self.register_from_headers(req.headers, "NT");
+                    }
+                    Some(nts) if nts == NTS_BYEBYE.as_bytes() => {
+                        if let Some(usn) =
+                            find_header(req.headers, "USN").and_then(|v| std::str::from_utf8(v).ok())
+                        {
+                            self.remove_device(usn);
+                        }
+                    }
+                    _ => trace!("ignoring NOTIFY with unknown NTS"),
+                }
+                return Ok(());
+            }
             Ok(m) => {
                 trace!("ignoring {m:?}");
                 return Ok(());
@@ -481,30 +960,49 @@ Content-Length: 0\r\n\r\n"
         }
     }
 
-    async fn try_send_example_msearch(&self) {
-        self.try_send_mcast_everywhere(&|opts| {
-            let dest = opts.addr_no_scope();
-            format!(
-                "M-SEARCH * HTTP/1.1\r
+    async fn task_send_msearch_periodically(&self) {
+        let mut interval = tokio::time::interval(self.opts.notify_interval);
+        loop {
+            interval.tick().await;
+            self.try_send_msearches().await;
+        }
+    }
+
+    async fn try_send_msearches(&self) {
+        // Solicit both other MediaServers and InternetGatewayDevices (the latter
+        // so we can later drive automatic port mapping for the listen port),
+        // rather than relying on catching IGDs passively via their NOTIFYs.
+        const SEARCH_TARGETS: [&str; 2] = [
+            "urn:schemas-upnp-org:device:MediaServer:1",
+            "urn:schemas-upnp-org:device:InternetGatewayDevice:1",
+        ];
+        for st in SEARCH_TARGETS {
+            self.try_send_mcast_everywhere(&|opts| {
+                let dest = opts.addr_no_scope();
+                format!(
+                    "M-SEARCH * HTTP/1.1\r
 HOST: {dest}\r
-ST: urn:schemas-upnp-org:device:MediaServer:1\r
+ST: {st}\r
 MAN: \"ssdp:discover\"\r
 MX: 2\r\n\r\n"
-            )
-            .into()
-        })
-        .await
+                )
+                .into()
+            })
+            .await
+        }
     }
 
     pub async fn run_forever(&self) -> anyhow::Result<()> {
-        // This isn't necessary, but would show that it works.
-        let t0 = self.try_send_example_msearch();
+        // Periodically search so the discovered-device registry stays fresh.
+        let t0 = self.task_send_msearch_periodically();
         let t1 = self.task_respond_on_msearches(self.socket_v4.as_ref());
         let t2 = self.task_respond_on_msearches(self.socket_v6.as_ref());
         let t3 = self.task_send_alive_notifies_periodically();
+        let t4 = self.task_resync_memberships_periodically();
+        let t5 = self.task_expire_devices();
 
         let wait = async move {
-            tokio::join!(t0, t1, t2, t3);
+            tokio::join!(t0, t1, t2, t3, t4, t5);
             Ok(())
         };
 
@@ -512,6 +1010,382 @@ MX: 2\r\n\r\n"
             r = wait => r,
             _ = self.opts.shutdown.cancelled() => {
                 self.try_send_notifies(NTS_BYEBYE).await;
+                self.drop_all_memberships();
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn bind_mdns_v4_socket() -> anyhow::Result<UdpSocket> {
+    let bind_addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_PORT);
+    let socket = socket_presetup(bind_addr.into())?;
+
+    let default_membership_ip = std::iter::once(Ipv4Addr::UNSPECIFIED);
+    let per_interface_ips = network_interface::NetworkInterface::show()
+        .into_iter()
+        .flatten()
+        .flat_map(|nic| nic.addr.into_iter())
+        .filter_map(|addr| match addr.ip() {
+            std::net::IpAddr::V4(addr) if !addr.is_loopback() => Some(addr),
+            _ => None,
+        });
+
+    for ifaddr in default_membership_ip.chain(per_interface_ips) {
+        trace!(multiaddr=?MDNS_MCAST_IPV4, interface=?ifaddr, "joining mDNS multicast v4 group");
+        if let Err(e) = socket.join_multicast_v4(MDNS_MCAST_IPV4, ifaddr) {
+            debug!(multiaddr=?MDNS_MCAST_IPV4, interface=?ifaddr, "error joining mDNS multicast v4 group: {e:#}");
+        }
+    }
+
+    Ok(socket)
+}
+
+async fn bind_mdns_v6_socket() -> anyhow::Result<UdpSocket> {
+    let bind_addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, MDNS_PORT, 0, 0);
+    let socket = socket_presetup(bind_addr.into())?;
+
+    for nic in network_interface::NetworkInterface::show()
+        .into_iter()
+        .flatten()
+    {
+        let has_v6 = nic.addr.iter().any(|addr| match addr.ip() {
+            IpAddr::V6(v6) => !v6.is_loopback(),
+            IpAddr::V4(_) => false,
+        });
+        if !has_v6 {
+            continue;
+        }
+        if let Err(e) = socket.join_multicast_v6(&MDNS_MCAST_IPV6, nic.index) {
+            debug!(multiaddr=?MDNS_MCAST_IPV6, interface=?nic.index, "error joining mDNS multicast v6 group: {e:#}");
+        }
+    }
+
+    Ok(socket)
+}
+
+pub struct MdnsRunnerOptions {
+    pub usn: String,
+    pub description_http_location: url::Url,
+    pub server_string: String,
+    pub announce_interval: Duration,
+    pub shutdown: CancellationToken,
+}
+
+/// A DNS-SD / mDNS responder that advertises the rqbit HTTP API as a
+/// [`MDNS_SERVICE_TYPE`] service, so zero-config tooling that speaks multicast
+/// DNS (Bonjour, Avahi, ...) rather than SSDP can discover it.
+pub struct MdnsRunner {
+    opts: MdnsRunnerOptions,
+    socket_v4: Option<UdpSocket>,
+    socket_v6: Option<UdpSocket>,
+    // "<instance>._http._tcp.local"
+    instance_fqdn: String,
+    // "<host>.local"
+    host_fqdn: String,
+    port: u16,
+}
+
+fn dns_encode_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        out.push(label.len() as u8);
+        out.extend_from_slice(label);
+    }
+    out.push(0);
+}
+
+fn dns_write_rr(out: &mut Vec<u8>, name: &str, rtype: u16, class: u16, ttl: u32, rdata: &[u8]) {
+    dns_encode_name(out, name);
+    out.extend_from_slice(&rtype.to_be_bytes());
+    out.extend_from_slice(&class.to_be_bytes());
+    out.extend_from_slice(&ttl.to_be_bytes());
+    out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    out.extend_from_slice(rdata);
+}
+
+/// Read a (possibly compressed) DNS name from `buf` starting at `pos`, returning
+/// the decoded name and the offset just past the name in the question/record.
+fn dns_read_name(buf: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    // Guard against pointer loops.
+    for _ in 0..128 {
+        let len = *buf.get(pos)? as usize;
+        if len == 0 {
+            pos += 1;
+            break;
+        }
+        if len & 0xc0 == 0xc0 {
+            let ptr = ((len & 0x3f) << 8) | (*buf.get(pos + 1)? as usize);
+            if end.is_none() {
+                end = Some(pos + 2);
+            }
+            pos = ptr;
+            continue;
+        }
+        let label = buf.get(pos + 1..pos + 1 + len)?;
+        if !name.is_empty() {
+            name.push('.');
+        }
+        name.push_str(&String::from_utf8_lossy(label));
+        pos += 1 + len;
+    }
+    Some((name, end.unwrap_or(pos)))
+}
+
+impl MdnsRunner {
+    pub async fn new(opts: MdnsRunnerOptions) -> anyhow::Result<Self> {
+        let socket_v4 = bind_mdns_v4_socket()
+            .await
+            .map_err(|e| warn!("error creating IPv4 mDNS socket: {e:#}"))
+            .ok();
+        let socket_v6 = bind_mdns_v6_socket()
+            .await
+            .map_err(|e| warn!("error creating IPv6 mDNS socket: {e:#}"))
+            .ok();
+
+        // Derive a stable, DNS-label-safe instance label from the USN, and a
+        // host name that the SRV record points at.
+        let label: String = opts
+            .usn
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let instance_fqdn = format!("{label}.{MDNS_SERVICE_TYPE}");
+        let host_fqdn = format!("{label}.local");
+        let port = opts
+            .description_http_location
+            .port_or_known_default()
+            .unwrap_or(80);
+
+        Ok(Self {
+            opts,
+            socket_v4,
+            socket_v6,
+            instance_fqdn,
+            host_fqdn,
+            port,
+        })
+    }
+
+    fn txt_rdata(&self) -> Vec<u8> {
+        let path = self.opts.description_http_location.path();
+        let entries = [
+            format!("server={}", self.opts.server_string),
+            format!("usn={}", self.opts.usn),
+            format!("path={path}"),
+        ];
+        let mut out = Vec::new();
+        for e in entries {
+            let bytes = &e.as_bytes()[..e.len().min(255)];
+            out.push(bytes.len() as u8);
+            out.extend_from_slice(bytes);
+        }
+        out
+    }
+
+    /// Build an mDNS answer for our service, including the address record for
+    /// `local_ip`. When `goodbye` is set the records carry a TTL of 0, which
+    /// tells caches to evict us (RFC 6762 §10.1).
+    fn build_answer(&self, local_ip: IpAddr, goodbye: bool) -> Vec<u8> {
+        let ttl = if goodbye { 0 } else { MDNS_TTL };
+        let mut msg = Vec::with_capacity(256);
+        // Header: id 0, flags 0x8400 (response, authoritative), 0 questions.
+        msg.extend_from_slice(&0u16.to_be_bytes());
+        msg.extend_from_slice(&0x8400u16.to_be_bytes());
+        msg.extend_from_slice(&0u16.to_be_bytes()); // qdcount
+        msg.extend_from_slice(&4u16.to_be_bytes()); // ancount: PTR, SRV, TXT, A/AAAA
+        msg.extend_from_slice(&0u16.to_be_bytes()); // nscount
+        msg.extend_from_slice(&0u16.to_be_bytes()); // arcount
+
+        // PTR _http._tcp.local -> <instance>
+        let mut ptr = Vec::new();
+        dns_encode_name(&mut ptr, &self.instance_fqdn);
+        dns_write_rr(&mut msg, MDNS_SERVICE_TYPE, DNS_TYPE_PTR, DNS_CLASS_IN, ttl, &ptr);
+
+        // SRV <instance> -> priority, weight, port, <host>
+        let mut srv = Vec::new();
+        srv.extend_from_slice(&0u16.to_be_bytes()); // priority
+        srv.extend_from_slice(&0u16.to_be_bytes()); // weight
+        srv.extend_from_slice(&self.port.to_be_bytes());
+        dns_encode_name(&mut srv, &self.host_fqdn);
+        dns_write_rr(
+            &mut msg,
+            &self.instance_fqdn,
+            DNS_TYPE_SRV,
+            DNS_CLASS_IN | DNS_CACHE_FLUSH,
+            ttl,
+            &srv,
+        );
+
+        // TXT <instance>
+        dns_write_rr(
+            &mut msg,
+            &self.instance_fqdn,
+            DNS_TYPE_TXT,
+            DNS_CLASS_IN | DNS_CACHE_FLUSH,
+            ttl,
+            &self.txt_rdata(),
+        );
+
+        // A / AAAA <host> -> local_ip
+        match local_ip {
+            IpAddr::V4(v4) => dns_write_rr(
+                &mut msg,
+                &self.host_fqdn,
+                DNS_TYPE_A,
+                DNS_CLASS_IN | DNS_CACHE_FLUSH,
+                ttl,
+                &v4.octets(),
+            ),
+            IpAddr::V6(v6) => dns_write_rr(
+                &mut msg,
+                &self.host_fqdn,
+                DNS_TYPE_AAAA,
+                DNS_CLASS_IN | DNS_CACHE_FLUSH,
+                ttl,
+                &v6.octets(),
+            ),
+        }
+
+        msg
+    }
+
+    /// True if the query contains a question that we are authoritative for.
+    fn query_matches(&self, buf: &[u8]) -> bool {
+        if buf.len() < 12 {
+            return false;
+        }
+        // Must be a query (QR bit clear).
+        if buf[2] & 0x80 != 0 {
+            return false;
+        }
+        let qdcount = u16::from_be_bytes([buf[4], buf[5]]) as usize;
+        let mut pos = 12;
+        for _ in 0..qdcount {
+            let (name, next) = match dns_read_name(buf, pos) {
+                Some(v) => v,
+                None => return false,
+            };
+            pos = next;
+            let qtype = match buf.get(pos..pos + 2) {
+                Some(b) => u16::from_be_bytes([b[0], b[1]]),
+                None => return false,
+            };
+            pos += 4; // qtype + qclass
+            let name_matches = name.eq_ignore_ascii_case(MDNS_SERVICE_TYPE)
+                || name.eq_ignore_ascii_case(&self.instance_fqdn)
+                || name.eq_ignore_ascii_case(&self.host_fqdn);
+            let type_matches = matches!(
+                qtype,
+                DNS_TYPE_PTR | DNS_TYPE_SRV | DNS_TYPE_TXT | DNS_TYPE_A | DNS_TYPE_AAAA | DNS_TYPE_ANY
+            );
+            if name_matches && type_matches {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Send an answer (an announcement, or a goodbye) out of every usable
+    /// interface, with the A/AAAA record carrying that interface's own address.
+    async fn announce_everywhere(&self, goodbye: bool) {
+        let interfaces = match network_interface::NetworkInterface::show() {
+            Ok(interfaces) => interfaces,
+            Err(e) => {
+                warn!(error=?e, "error determining network interfaces");
+                return;
+            }
+        };
+
+        let futs = interfaces
+            .into_iter()
+            .flat_map(|ni| ni.addr.into_iter().map(move |a| (ni.index, a.ip())))
+            .filter(|(_, ip)| !ip.is_loopback())
+            .map(|(ifidx, ip)| async move {
+                let payload = self.build_answer(ip, goodbye);
+                match ip {
+                    IpAddr::V4(v4) => {
+                        if let Some(sock) = self.socket_v4.as_ref() {
+                            if let Err(e) = set_mcast_if(sock, v4) {
+                                debug!(addr=%v4, "error calling set_mcast_if: {e:#}");
+                            }
+                            let dest = SocketAddr::V4(SocketAddrV4::new(MDNS_MCAST_IPV4, MDNS_PORT));
+                            if let Err(e) = sock.send_to(&payload, dest).await {
+                                debug!(addr=%dest, "error sending mDNS answer: {e:#}");
+                            }
+                        }
+                    }
+                    IpAddr::V6(_) => {
+                        if let Some(sock) = self.socket_v6.as_ref() {
+                            let dest = SocketAddr::V6(SocketAddrV6::new(
+                                MDNS_MCAST_IPV6,
+                                MDNS_PORT,
+                                0,
+                                ifidx,
+                            ));
+                            if let Err(e) = sock.send_to(&payload, dest).await {
+                                debug!(addr=%dest, "error sending mDNS answer: {e:#}");
+                            }
+                        }
+                    }
+                }
+            });
+
+        futures::future::join_all(futs).await;
+    }
+
+    async fn task_announce_periodically(&self) {
+        let mut interval = tokio::time::interval(self.opts.announce_interval);
+        loop {
+            interval.tick().await;
+            self.announce_everywhere(false).await;
+        }
+    }
+
+    async fn task_respond_on_queries(&self, sock: Option<&UdpSocket>) {
+        let mut buf = vec![0u8; 16184];
+        let sock = match sock {
+            Some(sock) => sock,
+            None => return,
+        };
+
+        loop {
+            let (sz, addr) = match sock.recv_from(&mut buf).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(error=?e, "error receiving mDNS message");
+                    return;
+                }
+            };
+            let msg = &buf[..sz];
+            if !self.query_matches(msg) {
+                continue;
+            }
+            trace!(?addr, "received matching mDNS query, responding");
+            // Respond to the multicast group rather than unicast so all caches refresh.
+            self.announce_everywhere(false).await;
+        }
+    }
+
+    pub async fn run_forever(&self) -> anyhow::Result<()> {
+        // Unsolicited announcement on startup so clients see us immediately.
+        self.announce_everywhere(false).await;
+
+        let t0 = self.task_respond_on_queries(self.socket_v4.as_ref());
+        let t1 = self.task_respond_on_queries(self.socket_v6.as_ref());
+        let t2 = self.task_announce_periodically();
+
+        let wait = async move {
+            tokio::join!(t0, t1, t2);
+            Ok(())
+        };
+
+        tokio::select! {
+            r = wait => r,
+            _ = self.opts.shutdown.cancelled() => {
+                self.announce_everywhere(true).await;
                 Ok(())
             }
         }